@@ -7,6 +7,10 @@ use solana_sdk::{
     address_lookup_table::state::{AddressLookupTable, LookupTableMeta, ProgramState},
     pubkey::Pubkey,
 };
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
 pub async fn load_address_lookup_table<'a>(
     rpc: &RpcClient,
@@ -25,6 +29,61 @@ pub async fn load_address_lookup_table<'a>(
         .collect::<Vec<_>>();
     Ok(accounts)
 }
+/// Memoizes deserialized `AddressLookupTableAccount`s keyed by `Pubkey`,
+/// refetching entries older than `ttl`.
+pub struct AltStore {
+    rpc: Arc<RpcClient>,
+    ttl: Duration,
+    cache: Mutex<HashMap<Pubkey, (AddressLookupTableAccount, Instant)>>,
+}
+
+impl AltStore {
+    pub fn new(rpc: Arc<RpcClient>, ttl: Duration) -> Self {
+        Self {
+            rpc,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fetches only the subset of `tables` that's missing or expired.
+    pub async fn get(&self, tables: &[Pubkey]) -> Result<Vec<AddressLookupTableAccount>> {
+        let mut resolved = Vec::with_capacity(tables.len());
+        let mut missing = Vec::new();
+        {
+            let cache = self.cache.lock().await;
+            for table in tables {
+                match cache.get(table) {
+                    Some((account, fetched_at)) if fetched_at.elapsed() < self.ttl => {
+                        resolved.push(account.clone());
+                    }
+                    _ => missing.push(*table),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            let fetched = load_address_lookup_table(&self.rpc, &missing).await?;
+            let mut cache = self.cache.lock().await;
+            let now = Instant::now();
+            for account in fetched {
+                cache.insert(account.key, (account.clone(), now));
+                resolved.push(account);
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    pub async fn invalidate(&self, table: &Pubkey) {
+        self.cache.lock().await.remove(table);
+    }
+
+    pub async fn clear(&self) {
+        self.cache.lock().await.clear();
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct LookupTable {
     pub meta: LookupTableMeta,
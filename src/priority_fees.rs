@@ -0,0 +1,100 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_response::RpcPrioritizationFee;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::swap_types::SwapResponse;
+
+/// `getRecentPrioritizationFees` rejects more than this many accounts.
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+/// Percentile tier to pick a priority fee from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityTier {
+    Max,
+    Min,
+    Median,
+    P75,
+    P90,
+    P95,
+}
+
+/// Percentile buckets over recent `getRecentPrioritizationFees` samples.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriorityFeeStats {
+    pub max: u64,
+    pub min: u64,
+    pub median: u64,
+    pub p75: Option<u64>,
+    pub p90: Option<u64>,
+    pub p95: Option<u64>,
+}
+
+impl PriorityFeeStats {
+    /// Falls back to the median if the percentile wasn't populated.
+    pub fn tier(&self, tier: PriorityTier) -> u64 {
+        match tier {
+            PriorityTier::Max => self.max,
+            PriorityTier::Min => self.min,
+            PriorityTier::Median => self.median,
+            PriorityTier::P75 => self.p75.unwrap_or(self.median),
+            PriorityTier::P90 => self.p90.unwrap_or(self.median),
+            PriorityTier::P95 => self.p95.unwrap_or(self.median),
+        }
+    }
+}
+
+/// Queries `getRecentPrioritizationFees` for the writable accounts touched
+/// by `swap_response` and buckets the results into percentiles.
+pub async fn recent_priority_fees(
+    rpc: &RpcClient,
+    swap_response: &SwapResponse,
+) -> Result<PriorityFeeStats> {
+    let accounts = writable_accounts(swap_response);
+    let fees = rpc.get_recent_prioritization_fees(&accounts).await?;
+    Ok(bucket_fees(fees))
+}
+
+fn writable_accounts(swap_response: &SwapResponse) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = swap_response
+        .setup_instructions
+        .iter()
+        .flat_map(|ix| ix.accounts.iter())
+        .chain(swap_response.swap_instruction.accounts.iter())
+        .filter(|acct| acct.is_writable)
+        .filter_map(|acct| acct.pubkey.parse().ok())
+        .collect();
+    accounts.sort_unstable();
+    accounts.dedup();
+    accounts.truncate(MAX_PRIORITIZATION_FEE_ACCOUNTS);
+    accounts
+}
+
+fn bucket_fees(fees: Vec<RpcPrioritizationFee>) -> PriorityFeeStats {
+    let mut values: Vec<u64> = fees.into_iter().map(|f| f.prioritization_fee).collect();
+    values.sort_unstable();
+
+    let len = values.len();
+    let max = values.last().copied().unwrap_or_default();
+    let min = values.first().copied().unwrap_or_default();
+    let median = values.get(len / 2).copied().unwrap_or_default();
+
+    let (p75, p90, p95) = if len > 1 {
+        (
+            values.get(len * 75 / 100).copied(),
+            values.get(len * 90 / 100).copied(),
+            values.get(len * 95 / 100).copied(),
+        )
+    } else {
+        (None, None, None)
+    };
+
+    PriorityFeeStats {
+        max,
+        min,
+        median,
+        p75,
+        p90,
+        p95,
+    }
+}
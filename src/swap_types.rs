@@ -1,17 +1,29 @@
-use crate::address_tables::load_address_lookup_table;
+use crate::address_tables::{load_address_lookup_table, AltStore};
 use crate::quote_types::QuoteResponse;
 use anyhow::anyhow;
 use base64::engine::general_purpose::STANDARD as b64;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::nonce::state::{State as NonceState, Versions as NonceVersions};
+use solana_sdk::signature::Signature;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
 use std::str::FromStr;
 use std::sync::Arc;
 pub const SWAP_BASE: &str = "https://quote-api.jup.ag/v6/swap-instructions";
 
+/// Headroom applied on top of simulated compute units.
+pub const DEFAULT_CU_MARGIN: f64 = 1.1;
+/// Per-transaction compute-unit ceiling.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwapRequest {
@@ -77,6 +89,30 @@ pub struct CleanupInstruction {
     pub data: String,
 }
 
+/// Recompiled message plus the units the simulation reported consumed.
+#[derive(Debug, Clone)]
+pub struct CuEstimate {
+    pub message: solana_sdk::message::v0::Message,
+    pub units_consumed: u64,
+}
+
+/// Compiles against a durable nonce instead of a recent blockhash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceConfig {
+    pub nonce_account: Pubkey,
+    pub nonce_authority: Pubkey,
+}
+
+async fn durable_nonce(rpc: &RpcClient, nonce_account: &Pubkey) -> anyhow::Result<Hash> {
+    let account = rpc.get_account(nonce_account).await?;
+    let versions: NonceVersions = bincode::deserialize(&account.data)
+        .map_err(|_| anyhow!("failed to parse nonce account data"))?;
+    match versions.state() {
+        NonceState::Uninitialized => Err(anyhow!("nonce account is uninitialized")),
+        NonceState::Initialized(data) => Ok(data.blockhash()),
+    }
+}
+
 impl SwapResponse {
     pub fn address_lookup_tables(&self) -> Vec<Pubkey> {
         self.address_lookup_table_addresses
@@ -84,20 +120,28 @@ impl SwapResponse {
             .filter_map(|addr| Pubkey::from_str(addr).ok())
             .collect::<Vec<_>>()
     }
-    pub async fn new_v0_transaction(
+
+    fn build_instructions(
         &self,
-        rpc: &Arc<RpcClient>,
-        payer: Pubkey,
         prio_fee: Option<u64>,
         cu_limit: Option<u32>,
-    ) -> anyhow::Result<solana_sdk::message::v0::Message> {
-        let num_instructions = usize::from(prio_fee.is_some())
+        nonce_config: Option<&NonceConfig>,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        let num_instructions = usize::from(nonce_config.is_some())
+            + usize::from(prio_fee.is_some())
             + usize::from(cu_limit.is_some())
             + self.setup_instructions.len()
             + 1; // 1 = swap tx
 
         let mut instructions = Vec::with_capacity(num_instructions);
 
+        if let Some(nonce_config) = nonce_config {
+            instructions.push(system_instruction::advance_nonce_account(
+                &nonce_config.nonce_account,
+                &nonce_config.nonce_authority,
+            ));
+        }
+
         if let Some(prio_fee) = prio_fee {
             instructions.push(ComputeBudgetInstruction::set_compute_unit_price(prio_fee));
         }
@@ -113,16 +157,105 @@ impl SwapResponse {
         instructions.extend_from_slice(&setup_ixs);
         instructions.push(self.swap_instruction.to_instruction()?);
         // omit cleanup
+        Ok(instructions)
+    }
+
+    async fn resolve_luts(
+        &self,
+        rpc: &Arc<RpcClient>,
+        alt_store: Option<&AltStore>,
+    ) -> anyhow::Result<Vec<AddressLookupTableAccount>> {
         let luts = self.address_lookup_tables();
-        let luts = load_address_lookup_table(rpc, &luts).await?;
-        let msg = solana_sdk::message::v0::Message::try_compile(
-            &payer,
-            &instructions,
-            &luts,
-            rpc.get_latest_blockhash().await?,
-        )?;
+        match alt_store {
+            Some(alt_store) => alt_store.get(&luts).await,
+            None => load_address_lookup_table(rpc, &luts).await,
+        }
+    }
+
+    async fn resolve_blockhash(
+        rpc: &Arc<RpcClient>,
+        nonce_config: Option<&NonceConfig>,
+    ) -> anyhow::Result<Hash> {
+        match nonce_config {
+            Some(nonce_config) => durable_nonce(rpc, &nonce_config.nonce_account).await,
+            None => Ok(rpc.get_latest_blockhash().await?),
+        }
+    }
+
+    pub async fn new_v0_transaction(
+        &self,
+        rpc: &Arc<RpcClient>,
+        payer: Pubkey,
+        prio_fee: Option<u64>,
+        cu_limit: Option<u32>,
+        alt_store: Option<&AltStore>,
+        nonce_config: Option<&NonceConfig>,
+    ) -> anyhow::Result<solana_sdk::message::v0::Message> {
+        let instructions = self.build_instructions(prio_fee, cu_limit, nonce_config)?;
+        let luts = self.resolve_luts(rpc, alt_store).await?;
+        let blockhash = Self::resolve_blockhash(rpc, nonce_config).await?;
+        let msg = solana_sdk::message::v0::Message::try_compile(&payer, &instructions, &luts, blockhash)?;
         Ok(msg)
     }
+
+    /// Simulates without a CU-limit instruction, then recompiles with
+    /// `set_compute_unit_limit(units_consumed * margin)`, rounded up and
+    /// capped at `MAX_COMPUTE_UNIT_LIMIT`.
+    pub async fn new_v0_transaction_with_cu_estimate(
+        &self,
+        rpc: &Arc<RpcClient>,
+        payer: Pubkey,
+        prio_fee: Option<u64>,
+        margin: f64,
+        alt_store: Option<&AltStore>,
+        nonce_config: Option<&NonceConfig>,
+    ) -> anyhow::Result<CuEstimate> {
+        let luts = self.resolve_luts(rpc, alt_store).await?;
+        let blockhash = Self::resolve_blockhash(rpc, nonce_config).await?;
+
+        let unsized_ixs = self.build_instructions(prio_fee, None, nonce_config)?;
+        let unsized_msg =
+            solana_sdk::message::v0::Message::try_compile(&payer, &unsized_ixs, &luts, blockhash)?;
+        let sim_tx = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                unsized_msg.header.num_required_signatures as usize
+            ],
+            message: VersionedMessage::V0(unsized_msg),
+        };
+        let sim = rpc.simulate_transaction(&sim_tx).await?;
+        let units_consumed = sim
+            .value
+            .units_consumed
+            .ok_or_else(|| anyhow!("simulation did not report units_consumed"))?;
+        let cu_limit = ((units_consumed as f64) * margin).ceil() as u32;
+        let cu_limit = cu_limit.min(MAX_COMPUTE_UNIT_LIMIT);
+
+        let sized_ixs = self.build_instructions(prio_fee, Some(cu_limit), nonce_config)?;
+        let message =
+            solana_sdk::message::v0::Message::try_compile(&payer, &sized_ixs, &luts, blockhash)?;
+        Ok(CuEstimate {
+            message,
+            units_consumed,
+        })
+    }
+
+    /// Quote the lamport fee for `message` via `getFeeForMessage`.
+    /// Returns `None` only when the message's blockhash has expired;
+    /// other RPC failures are propagated.
+    pub async fn estimate_fee(
+        rpc: &RpcClient,
+        message: &solana_sdk::message::v0::Message,
+    ) -> anyhow::Result<Option<u64>> {
+        match rpc
+            .get_fee_for_message(&VersionedMessage::V0(message.clone()))
+            .await
+        {
+            Ok(fee) => Ok(Some(fee)),
+            Err(err) if err.to_string().contains("blockhash") => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
 }
 
 impl SetupInstruction {
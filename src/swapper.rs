@@ -1,14 +1,20 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
-use solana_sdk::{signature::{Keypair, Signer, Signature}, transaction::VersionedTransaction, message::VersionedMessage};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig}};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::{Keypair, Signer, Signature}, transaction::VersionedTransaction, message::VersionedMessage};
+use solana_transaction_status::UiTransactionEncoding;
 use anyhow::{Result, anyhow, Context};
-use crate::swap_types::SwapResponse;
+use tokio::time::sleep;
+use crate::address_tables::AltStore;
+use crate::swap_types::{SwapResponse, DEFAULT_CU_MARGIN};
+use crate::priority_fees::{recent_priority_fees, PriorityTier};
 
 #[derive(Clone)]
 pub struct Swapper {
     pub rpc: Arc<RpcClient>,
-    pub keypair_bytes: [u8; 64]
+    pub keypair_bytes: [u8; 64],
+    pub alt_store: Option<Arc<AltStore>>,
 }
 
 impl Swapper {
@@ -16,13 +22,39 @@ impl Swapper {
         Self {
             rpc,
             keypair_bytes: keypair.to_bytes(),
+            alt_store: None,
         }
     }
-    pub async fn new_swap(self: &Arc<Self>, swap_response: SwapResponse, skip_preflight: bool, retries: usize) -> Result<Signature> {
+    /// Like `new`, but caches address lookup tables for `alt_ttl` instead of
+    /// re-fetching them on every swap.
+    pub fn new_with_alt_store(rpc: Arc<RpcClient>, keypair: Keypair, alt_ttl: Duration) -> Swapper {
+        let alt_store = Arc::new(AltStore::new(rpc.clone(), alt_ttl));
+        Self {
+            rpc,
+            keypair_bytes: keypair.to_bytes(),
+            alt_store: Some(alt_store),
+        }
+    }
+    pub async fn new_swap(self: &Arc<Self>, swap_response: SwapResponse, skip_preflight: bool, retries: usize, priority_tier: PriorityTier) -> Result<Signature> {
         let kp = Keypair::from_bytes(&self.keypair_bytes)?;
-        let v0_msg = swap_response.new_v0_transaction(&self.rpc, kp.pubkey(), Some(prio_fee(0.001)), Some(1_000_000)).await?;
+        // fall back to the old fixed fee if the fee-estimation RPC call fails
+        // or the node doesn't expose recent prioritization fees
+        let prio = match recent_priority_fees(&self.rpc, &swap_response).await {
+            Ok(stats) => stats.tier(priority_tier),
+            Err(_) => prio_fee(0.001),
+        };
+        let cu_estimate = swap_response
+            .new_v0_transaction_with_cu_estimate(
+                &self.rpc,
+                kp.pubkey(),
+                Some(prio),
+                DEFAULT_CU_MARGIN,
+                self.alt_store.as_deref(),
+                None,
+            )
+            .await?;
         let v_tx = VersionedTransaction::try_new(
-            VersionedMessage::V0(v0_msg),
+            VersionedMessage::V0(cu_estimate.message),
             &vec![&kp]
         )?;
         match self.rpc.send_transaction_with_config(
@@ -43,6 +75,66 @@ impl Swapper {
         // if this fails something fucked up
         Keypair::from_bytes(&self.keypair_bytes).unwrap()
     }
+    pub async fn quote_fee(self: &Arc<Self>, message: &solana_sdk::message::v0::Message) -> Result<Option<u64>> {
+        SwapResponse::estimate_fee(&self.rpc, message).await
+    }
+    /// Polls until `signature` reaches `commitment`, then fetches its meta.
+    pub async fn confirm_swap(
+        self: &Arc<Self>,
+        signature: Signature,
+        commitment: CommitmentConfig,
+        max_attempts: usize,
+    ) -> Result<SwapOutcome> {
+        for _ in 0..max_attempts {
+            let statuses = self.rpc.get_signature_statuses(&[signature]).await?.value;
+            if let Some(Some(status)) = statuses.into_iter().next() {
+                if status.satisfies_commitment(commitment) {
+                    return self.fetch_swap_outcome(signature).await;
+                }
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        Err(anyhow!("timed out waiting for {signature} to reach {commitment:?}"))
+    }
+    async fn fetch_swap_outcome(self: &Arc<Self>, signature: Signature) -> Result<SwapOutcome> {
+        let tx = self
+            .rpc
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    commitment: None,
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .with_context(|| format!("failed to fetch transaction meta for {signature}"))?;
+
+        let meta = tx
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow!("missing transaction meta for {signature}"))?;
+
+        Ok(SwapOutcome {
+            signature,
+            slot: Some(tx.slot),
+            fee: Some(meta.fee),
+            compute_units_consumed: Option::from(meta.compute_units_consumed),
+            err: meta.err.map(|err| err.to_string()),
+            logs: Option::from(meta.log_messages).unwrap_or_default(),
+        })
+    }
+}
+
+/// Outcome of a confirmed swap: whether it landed, what it cost, and logs.
+#[derive(Debug, Clone)]
+pub struct SwapOutcome {
+    pub signature: Signature,
+    pub slot: Option<u64>,
+    pub fee: Option<u64>,
+    pub compute_units_consumed: Option<u64>,
+    pub err: Option<String>,
+    pub logs: Vec<String>,
 }
 
 pub fn prio_fee(input: f64) -> u64 {